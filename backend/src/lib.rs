@@ -1,18 +1,24 @@
 use ic_cdk::api::caller;
 use ic_cdk::api::management_canister::ecdsa::{
-    ecdsa_public_key, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument,
+    ecdsa_public_key, sign_with_ecdsa, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument,
+    SignWithEcdsaArgument,
 };
 use ic_cdk::api::management_canister::bitcoin::{
-    bitcoin_get_utxos, 
-    bitcoin_get_current_fee_percentiles, 
-    BitcoinNetwork as IcpBitcoinNetwork, 
-    GetUtxosRequest,
-    GetCurrentFeePercentilesRequest,
-    Utxo 
+    bitcoin_get_current_fee_percentiles, bitcoin_get_utxos, bitcoin_send_transaction,
+    BitcoinNetwork as IcpBitcoinNetwork, GetCurrentFeePercentilesRequest, GetUtxosRequest,
+    SendTransactionRequest, Utxo, UtxoFilter,
 };
-use bitcoin::{Address, Network, PublicKey};
+use bitcoin::blockdata::script::Script;
+use bitcoin::blockdata::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use bitcoin::blockdata::witness::Witness;
+use bitcoin::consensus::encode::serialize;
+use bitcoin::hashes::Hash;
+use bitcoin::util::bip143::SighashComponents;
+use bitcoin::{Address, Network, PublicKey, SigHashType, Txid};
 use candid::{CandidType, Deserialize};
 use serde::Serialize;
+use std::cell::RefCell;
+use std::str::FromStr;
 
 // --- DATA STRUCTURES ---
 #[derive(CandidType, Serialize, Deserialize, Debug)]
@@ -23,99 +29,504 @@ pub struct AddressInfo {
     pub utxos: Vec<Utxo>,
 }
 
+// Below the dust limit a P2WPKH output can't be relayed, so change that small gets folded into
+// the fee instead of being sent back to the sender.
+const DUST_THRESHOLD_SATS: u64 = 294;
+
 // --- CONFIGURATION ---
+// Runtime-configurable so the same build can be deployed against testnet/regtest during
+// development and mainnet in production without a recompile. Set via `init`, stashed into stable
+// memory in `pre_upgrade`, and restored in `post_upgrade` so an upgrade keeps the running config.
+// The Testnet/test_key_1 default applies only when no config argument is passed and nothing can be
+// restored from stable memory.
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
+pub struct CanisterConfig {
+    pub network: IcpBitcoinNetwork,
+    pub key_name: String,
+}
+
+impl Default for CanisterConfig {
+    fn default() -> Self {
+        Self {
+            network: IcpBitcoinNetwork::Testnet,
+            key_name: "test_key_1".to_string(),
+        }
+    }
+}
+
+thread_local! {
+    static CONFIG: RefCell<CanisterConfig> = RefCell::new(CanisterConfig::default());
+}
+
+// Mainnet must be paired with the production key; mixing a test key into a mainnet deployment
+// (or vice versa) would either fail outright or, worse, derive addresses nobody can later match
+// against the intended key.
+fn validate_config(config: &CanisterConfig) {
+    let mainnet_key = config.key_name == "key_1";
+    let is_mainnet = matches!(config.network, IcpBitcoinNetwork::Mainnet);
+    if is_mainnet != mainnet_key {
+        panic!(
+            "Invalid config: the \"key_1\" production key must be used with Mainnet and only with Mainnet, got network {:?} with key name \"{}\"",
+            config.network, config.key_name
+        );
+    }
+}
+
+fn set_config(config: CanisterConfig) {
+    validate_config(&config);
+    CONFIG.with(|c| *c.borrow_mut() = config);
+}
+
+#[ic_cdk::init]
+fn init(config: Option<CanisterConfig>) {
+    set_config(config.unwrap_or_default());
+}
+
+// Stash the live config into stable memory so `post_upgrade` can restore it even when the caller
+// doesn't re-pass it (e.g. a `dfx deploy` upgrade with no explicit config argument).
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    let config = CONFIG.with(|c| c.borrow().clone());
+    ic_cdk::storage::stable_save((config,)).expect("Failed to save config to stable memory");
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade(config: Option<CanisterConfig>) {
+    // An explicit argument always wins; otherwise restore what was running before the upgrade
+    // rather than silently resetting to the Testnet/test_key_1 default.
+    let restored = config.or_else(|| {
+        ic_cdk::storage::stable_restore::<(CanisterConfig,)>()
+            .ok()
+            .map(|(config,)| config)
+    });
+    set_config(restored.unwrap_or_default());
+}
+
+// Lets operators confirm which network and key the canister is bound to before funding any
+// addresses derived from it.
+#[ic_cdk::query]
+fn get_config() -> CanisterConfig {
+    CONFIG.with(|c| c.borrow().clone())
+}
+
 fn get_key_id() -> EcdsaKeyId {
     EcdsaKeyId {
         curve: EcdsaCurve::Secp256k1,
-        name: "test_key_1".to_string(), 
+        name: CONFIG.with(|c| c.borrow().key_name.clone()),
     }
 }
 
 fn get_network() -> Network {
-    Network::Testnet 
+    match get_icp_network() {
+        IcpBitcoinNetwork::Mainnet => Network::Bitcoin,
+        IcpBitcoinNetwork::Testnet => Network::Testnet,
+        IcpBitcoinNetwork::Regtest => Network::Regtest,
+    }
 }
 
 fn get_icp_network() -> IcpBitcoinNetwork {
-    IcpBitcoinNetwork::Testnet 
+    CONFIG.with(|c| c.borrow().network)
+}
+
+// --- ADDRESS TYPES ---
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    Legacy,      // P2PKH
+    NestedSegwit, // P2SH-P2WPKH
+    NativeSegwit, // P2WPKH
+    Taproot,     // P2TR
+}
+
+impl Default for AddressType {
+    fn default() -> Self {
+        AddressType::NativeSegwit
+    }
+}
+
+// A principal alone derives one key; appending an account index lets a single principal hand out
+// many independent receive addresses (one key per account), mirroring BIP32 account derivation.
+fn derivation_path_for(p: &candid::Principal, account: Option<u32>) -> Vec<Vec<u8>> {
+    match account {
+        Some(account) => vec![p.as_slice().to_vec(), account.to_be_bytes().to_vec()],
+        None => vec![p.as_slice().to_vec()],
+    }
 }
 
 // --- HELPER: DERIVE ADDRESS ---
-async fn derive_address_for_principal(p: candid::Principal) -> String {
+async fn fetch_public_key(p: &candid::Principal, account: Option<u32>) -> PublicKey {
     let (pk_response,) = ecdsa_public_key(EcdsaPublicKeyArgument {
         canister_id: None,
-        derivation_path: vec![p.as_slice().to_vec()],
+        derivation_path: derivation_path_for(p, account),
         key_id: get_key_id(),
-    }).await.expect("Failed to fetch public key");
+    })
+    .await
+    .expect("Failed to fetch public key");
+
+    PublicKey::from_slice(&pk_response.public_key).expect("Invalid public key")
+}
+
+fn address_for(public_key: &PublicKey, address_type: AddressType, network: Network) -> Address {
+    match address_type {
+        AddressType::Legacy => Address::p2pkh(public_key, network),
+        AddressType::NestedSegwit => {
+            Address::p2shwpkh(public_key, network).expect("Failed to create address")
+        }
+        AddressType::NativeSegwit => {
+            Address::p2wpkh(public_key, network).expect("Failed to create address")
+        }
+        AddressType::Taproot => {
+            let secp = bitcoin::secp256k1::Secp256k1::verification_only();
+            let (x_only_public_key, _parity) = public_key.inner.x_only_public_key();
+            Address::p2tr(&secp, x_only_public_key, None, network)
+        }
+    }
+}
 
-    let public_key = PublicKey::from_slice(&pk_response.public_key)
-        .expect("Invalid public key");
-        
-    Address::p2wpkh(&public_key, get_network())
-        .expect("Failed to create address")
-        .to_string()
+async fn derive_address_for_principal(
+    p: candid::Principal,
+    address_type: AddressType,
+    account: Option<u32>,
+) -> String {
+    let public_key = fetch_public_key(&p, account).await;
+    address_for(&public_key, address_type, get_network()).to_string()
+}
+
+// --- HELPER: PAGINATED UTXO FETCH ---
+// `bitcoin_get_utxos` caps each response and returns a `next_page` token when an address holds
+// more UTXOs than fit in one page. Loop until the token is empty so balances and counts on busy
+// addresses aren't silently under-reported.
+async fn get_all_utxos(
+    address: String,
+    network: IcpBitcoinNetwork,
+    min_confirmations: Option<u32>,
+) -> Vec<Utxo> {
+    let mut utxos = Vec::new();
+    let mut page_filter = min_confirmations.map(UtxoFilter::MinConfirmations);
+
+    loop {
+        let (response,) = bitcoin_get_utxos(GetUtxosRequest {
+            network,
+            address: address.clone(),
+            filter: page_filter.clone(),
+        })
+        .await
+        .expect("Failed to fetch UTXOs.");
+
+        utxos.extend(response.utxos);
+
+        if response.next_page.is_empty() {
+            break;
+        }
+        page_filter = Some(UtxoFilter::Page(response.next_page));
+    }
+
+    utxos
 }
 
 // --- FUNCTION 1: GET ADDRESS ---
 #[ic_cdk::update]
-async fn get_btc_address() -> String {
-    derive_address_for_principal(caller()).await
+async fn get_btc_address(address_type: Option<AddressType>, account: Option<u32>) -> String {
+    derive_address_for_principal(caller(), address_type.unwrap_or_default(), account).await
 }
 
 // --- FUNCTION 2: MASTER UTXO & BALANCE CHECKER ---
 #[ic_cdk::update]
-async fn get_utxos_and_balance(target_address: Option<String>) -> AddressInfo {
+async fn get_utxos_and_balance(
+    target_address: Option<String>,
+    min_confirmations: Option<u32>,
+    address_type: Option<AddressType>,
+    account: Option<u32>,
+) -> AddressInfo {
     // 1. Determine Address
     let address_to_check = match target_address {
         Some(addr) => addr.trim().to_string(),
-        None => derive_address_for_principal(caller()).await,
+        None => {
+            derive_address_for_principal(caller(), address_type.unwrap_or_default(), account).await
+        }
     };
 
-    // 2. Fetch UTXOs
-    let (response,) = bitcoin_get_utxos(GetUtxosRequest {
-        network: get_icp_network(), 
-        address: address_to_check.clone(),
-        filter: None, 
-    })
-    .await
-    .expect("Failed to fetch UTXOs.");
+    // 2. Fetch UTXOs (all pages)
+    let utxos = get_all_utxos(address_to_check.clone(), get_icp_network(), min_confirmations).await;
 
     // 3. Calculate Totals
     let mut total_sats = 0;
-    for utxo in &response.utxos {
+    for utxo in &utxos {
         total_sats += utxo.value;
     }
-    
+
     // Calculate Count
-    let count = response.utxos.len() as u32;
+    let count = utxos.len() as u32;
 
     // 4. Return Data
     AddressInfo {
         address: address_to_check,
         balance_sats: total_sats,
         utxo_count: count, // Returns the number (e.g., 3)
-        utxos: response.utxos,
+        utxos,
     }
 }
 
 // --- NEW FUNCTION: GET ONLY THE COUNT ---
 // Returns just the number (e.g., 5) for simpler logic checks
 #[ic_cdk::update]
-async fn get_utxo_count_only(target_address: Option<String>) -> u32 {
+async fn get_utxo_count_only(
+    target_address: Option<String>,
+    min_confirmations: Option<u32>,
+    address_type: Option<AddressType>,
+    account: Option<u32>,
+) -> u32 {
     let address_to_check = match target_address {
         Some(addr) => addr.trim().to_string(),
-        None => derive_address_for_principal(caller()).await,
+        None => {
+            derive_address_for_principal(caller(), address_type.unwrap_or_default(), account).await
+        }
     };
 
-    let (response,) = bitcoin_get_utxos(GetUtxosRequest {
-        network: get_icp_network(), 
-        address: address_to_check,
-        filter: None, 
+    let utxos = get_all_utxos(address_to_check, get_icp_network(), min_confirmations).await;
+
+    // Return just the length of the vector
+    utxos.len() as u32
+}
+
+// --- HELPER: FEE ESTIMATION ---
+// Rough vbyte cost of a P2WPKH spend: ~68 vbytes/input, ~31 vbytes/output, plus version/locktime
+// overhead. Good enough for fee previews and dust decisions; not a consensus-critical estimate.
+fn estimate_fee_sats(num_inputs: usize, num_outputs: usize, millisatoshi_per_vbyte: u64) -> u64 {
+    let estimated_vsize = (num_inputs * 68 + num_outputs * 31 + 11) as u64;
+    (estimated_vsize * millisatoshi_per_vbyte) / 1000
+}
+
+async fn get_fee_per_vbyte() -> u64 {
+    let (percentiles,) = bitcoin_get_current_fee_percentiles(GetCurrentFeePercentilesRequest {
+        network: get_icp_network(),
     })
     .await
-    .expect("Failed to fetch UTXOs.");
+    .expect("Failed to fetch fee percentiles.");
 
-    // Return just the length of the vector
-    response.utxos.len() as u32
+    if percentiles.is_empty() {
+        // No mempool data yet (e.g. regtest): fall back to a conservative default.
+        2000
+    } else {
+        percentiles[percentiles.len() / 2]
+    }
+}
+
+// --- HELPER: SIGNATURE ENCODING ---
+// `sign_with_ecdsa` returns a 64-byte compact (r || s) signature. Bitcoin wants DER, so re-encode
+// it by hand rather than pulling in a signing library we don't otherwise need.
+fn sec1_to_der(sig: Vec<u8>) -> Vec<u8> {
+    let r = der_encode_integer(&sig[..32]);
+    let s = der_encode_integer(&sig[32..]);
+    let mut der = vec![0x02, r.len() as u8];
+    der.extend(r);
+    der.push(0x02);
+    der.push(s.len() as u8);
+    der.extend(s);
+    der.insert(0, der.len() as u8);
+    der.insert(0, 0x30);
+    der
+}
+
+// BIP66 strict DER requires minimally-encoded integers: no leading 0x00 bytes, except the single
+// one needed to keep the value non-negative when the high bit of the first remaining byte is set.
+fn der_encode_integer(component: &[u8]) -> Vec<u8> {
+    let mut trimmed = component;
+    while trimmed.len() > 1 && trimmed[0] == 0x00 {
+        trimmed = &trimmed[1..];
+    }
+    if trimmed[0] & 0x80 != 0 {
+        [vec![0x00], trimmed.to_vec()].concat()
+    } else {
+        trimmed.to_vec()
+    }
+}
+
+// --- HELPER: COIN SELECTION ---
+// Fetches UTXOs and the current fee rate, then selects inputs for `amount_sats`. Shared by
+// `select_utxos_fee` (so the UI can preview cost) and `send_btc`, so both apply the same selection
+// rule. Each call fetches UTXOs and the fee rate afresh, so `send_btc` spends the previewed inputs
+// only when neither has changed since the preview.
+async fn select_utxos(
+    address: String,
+    amount_sats: u64,
+    min_confirmations: Option<u32>,
+) -> (Vec<Utxo>, u64, u64) {
+    let candidates = get_all_utxos(address, get_icp_network(), min_confirmations).await;
+    let fee_per_vbyte = get_fee_per_vbyte().await;
+    select_utxos_for_amount(candidates, amount_sats, fee_per_vbyte)
+}
+
+// Pure accumulative selection: largest UTXOs first, recomputing the estimated fee after each
+// addition until the running total covers `amount_sats + fee`. Split out from `select_utxos` so
+// the selection math can be unit tested without the management canister calls.
+fn select_utxos_for_amount(
+    mut candidates: Vec<Utxo>,
+    amount_sats: u64,
+    fee_per_vbyte: u64,
+) -> (Vec<Utxo>, u64, u64) {
+    candidates.sort_by(|a, b| b.value.cmp(&a.value));
+
+    let mut selected: Vec<Utxo> = Vec::new();
+    let mut total_input_sats = 0u64;
+    for utxo in candidates {
+        selected.push(utxo.clone());
+        total_input_sats += utxo.value;
+        let fee = estimate_fee_sats(selected.len(), 2, fee_per_vbyte);
+        if total_input_sats >= amount_sats + fee {
+            break;
+        }
+    }
+
+    let fee = estimate_fee_sats(selected.len(), 2, fee_per_vbyte);
+    if total_input_sats < amount_sats + fee {
+        panic!(
+            "Insufficient funds: have {total_input_sats} sats, need {} sats (including fee)",
+            amount_sats + fee
+        );
+    }
+
+    // Sorted deterministically by outpoint so the signed sighash order always matches the order
+    // the transaction is ultimately broadcast in.
+    selected.sort_by(|a, b| {
+        (a.outpoint.txid.clone(), a.outpoint.vout).cmp(&(b.outpoint.txid.clone(), b.outpoint.vout))
+    });
+
+    (selected, fee, total_input_sats)
+}
+
+#[derive(CandidType, Serialize, Deserialize, Debug)]
+pub struct SelectedUtxosFeeResponse {
+    pub selected: Vec<Utxo>,
+    pub fee_sats: u64,
+    pub total_input_sats: u64,
+}
+
+// --- FUNCTION: COIN SELECTION PREVIEW ---
+// Lets a frontend preview which UTXOs would fund `amount_sats` and what fee that costs, before
+// the caller commits to `send_btc`.
+#[ic_cdk::update]
+async fn select_utxos_fee(
+    amount_sats: u64,
+    min_confirmations: Option<u32>,
+    address_type: Option<AddressType>,
+    account: Option<u32>,
+) -> SelectedUtxosFeeResponse {
+    let address =
+        derive_address_for_principal(caller(), address_type.unwrap_or_default(), account).await;
+    let (selected, fee_sats, total_input_sats) =
+        select_utxos(address, amount_sats, min_confirmations).await;
+
+    SelectedUtxosFeeResponse {
+        selected,
+        fee_sats,
+        total_input_sats,
+    }
+}
+
+// --- FUNCTION: SEND BTC ---
+// Builds, signs (threshold ECDSA), and broadcasts a transaction on behalf of the caller, spending
+// from (and returning change to) the NativeSegwit address of the given `account`, the same one
+// `get_btc_address` and `select_utxos_fee` derive for it. Any other `address_type` is rejected
+// with a panic (see the guard below).
+#[ic_cdk::update]
+async fn send_btc(
+    destination: String,
+    amount_sats: u64,
+    min_confirmations: Option<u32>,
+    address_type: Option<AddressType>,
+    account: Option<u32>,
+) -> String {
+    let principal = caller();
+    let address_type = address_type.unwrap_or_default();
+    // Signing below is BIP143/P2WPKH-specific (ECDSA over a P2PKH-shaped script_code, pushed into
+    // a segwit witness); Legacy and NestedSegwit need a different sighash algorithm and Taproot
+    // needs Schnorr signatures, none of which this canister implements yet. Guard rather than
+    // silently building an unspendable or wrongly-signed transaction.
+    if address_type != AddressType::NativeSegwit {
+        panic!("send_btc only supports spending from NativeSegwit (P2WPKH) addresses today");
+    }
+    let public_key = fetch_public_key(&principal, account).await;
+    let sender_address = address_for(&public_key, address_type, get_network()).to_string();
+
+    let destination_address = Address::from_str(&destination)
+        .expect("Invalid destination address")
+        .require_network(get_network())
+        .expect("Destination address is for the wrong network");
+
+    let (selected, fee, total_input_sats) =
+        select_utxos(sender_address.clone(), amount_sats, min_confirmations).await;
+
+    let change_sats = total_input_sats - amount_sats - fee;
+
+    let inputs: Vec<TxIn> = selected
+        .iter()
+        .map(|utxo| TxIn {
+            previous_output: OutPoint {
+                txid: Txid::from_hash(
+                    Hash::from_slice(&utxo.outpoint.txid).expect("Invalid txid bytes"),
+                ),
+                vout: utxo.outpoint.vout,
+            },
+            script_sig: Script::new(),
+            sequence: 0xffffffff,
+            witness: Witness::new(),
+        })
+        .collect();
+
+    let mut outputs = vec![TxOut {
+        value: amount_sats,
+        script_pubkey: destination_address.script_pubkey(),
+    }];
+    if change_sats >= DUST_THRESHOLD_SATS {
+        outputs.push(TxOut {
+            value: change_sats,
+            script_pubkey: address_for(&public_key, address_type, get_network()).script_pubkey(),
+        });
+    }
+
+    let mut transaction = Transaction {
+        version: 1,
+        lock_time: 0,
+        input: inputs,
+        output: outputs,
+    };
+
+    let sighash_components = SighashComponents::new(&transaction);
+    let pubkey_hash = public_key.pubkey_hash();
+    // P2WPKH script_code per BIP143: OP_DUP OP_HASH160 <pubkeyhash> OP_EQUALVERIFY OP_CHECKSIG.
+    let script_code = Script::new_p2pkh(&pubkey_hash);
+
+    for (index, utxo) in selected.iter().enumerate() {
+        let sighash =
+            sighash_components.sighash_all(&transaction.input[index], &script_code, utxo.value);
+
+        let (signature_reply,) = sign_with_ecdsa(SignWithEcdsaArgument {
+            message_hash: sighash.to_vec(),
+            derivation_path: derivation_path_for(&principal, account),
+            key_id: get_key_id(),
+        })
+        .await
+        .expect("Failed to sign with ECDSA");
+
+        let mut der_signature = sec1_to_der(signature_reply.signature);
+        der_signature.push(SigHashType::All as u8);
+
+        let mut witness = Witness::new();
+        witness.push(&der_signature);
+        witness.push(&public_key.to_bytes());
+        transaction.input[index].witness = witness;
+    }
+
+    let signed_transaction_bytes = serialize(&transaction);
+
+    bitcoin_send_transaction(SendTransactionRequest {
+        network: get_icp_network(),
+        transaction: signed_transaction_bytes,
+    })
+    .await
+    .expect("Failed to broadcast transaction");
+
+    transaction.txid().to_string()
 }
 
 // --- DEBUG STATUS ---
@@ -130,4 +541,84 @@ async fn debug_network_status() -> String {
     }
 }
 
-ic_cdk::export_candid!();
\ No newline at end of file
+ic_cdk::export_candid!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxo(txid_byte: u8, vout: u32, value: u64) -> Utxo {
+        Utxo {
+            outpoint: ic_cdk::api::management_canister::bitcoin::Outpoint {
+                txid: vec![txid_byte; 32],
+                vout,
+            },
+            value,
+            height: 0,
+        }
+    }
+
+    #[test]
+    fn sec1_to_der_strips_leading_zero_without_flipping_sign() {
+        // r has a leading zero byte that must be dropped for minimal DER encoding.
+        let mut sig = vec![0u8; 64];
+        sig[0] = 0x00;
+        sig[1] = 0x01;
+        sig[32] = 0x01;
+        let der = sec1_to_der(sig);
+
+        assert_eq!(der[0], 0x30);
+        let r_len = der[3] as usize;
+        assert_eq!(r_len, 31, "leading zero byte should have been trimmed");
+        assert_eq!(der[4], 0x01);
+    }
+
+    #[test]
+    fn sec1_to_der_keeps_one_zero_when_high_bit_set() {
+        // r with its top bit set needs exactly one 0x00 prefix to stay a positive DER integer.
+        let mut sig = vec![0u8; 64];
+        sig[0] = 0x80;
+        sig[32] = 0x01;
+        let der = sec1_to_der(sig);
+
+        let r_len = der[3] as usize;
+        assert_eq!(r_len, 33);
+        assert_eq!(der[4], 0x00);
+        assert_eq!(der[5], 0x80);
+    }
+
+    #[test]
+    fn estimate_fee_sats_scales_with_inputs_and_rate() {
+        assert_eq!(estimate_fee_sats(1, 2, 1000), 68 + 31 * 2 + 11);
+        assert_eq!(estimate_fee_sats(2, 2, 1000), 68 * 2 + 31 * 2 + 11);
+        assert_eq!(estimate_fee_sats(1, 2, 0), 0);
+    }
+
+    #[test]
+    fn select_utxos_for_amount_stops_once_amount_plus_fee_is_covered() {
+        let candidates = vec![utxo(1, 0, 100_000), utxo(2, 0, 50_000), utxo(3, 0, 10_000)];
+        let (selected, fee, total_input_sats) =
+            select_utxos_for_amount(candidates, 80_000, 1000);
+
+        assert_eq!(selected.len(), 1, "the single largest UTXO already covers the amount + fee");
+        assert_eq!(total_input_sats, 100_000);
+        assert_eq!(fee, estimate_fee_sats(1, 2, 1000));
+    }
+
+    #[test]
+    fn select_utxos_for_amount_accumulates_across_multiple_inputs() {
+        let candidates = vec![utxo(1, 0, 30_000), utxo(2, 0, 30_000), utxo(3, 0, 30_000)];
+        let (selected, _fee, total_input_sats) =
+            select_utxos_for_amount(candidates, 55_000, 1000);
+
+        assert_eq!(selected.len(), 2);
+        assert_eq!(total_input_sats, 60_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient funds")]
+    fn select_utxos_for_amount_panics_when_total_is_insufficient() {
+        let candidates = vec![utxo(1, 0, 10_000)];
+        select_utxos_for_amount(candidates, 50_000, 1000);
+    }
+}
\ No newline at end of file